@@ -1,11 +1,17 @@
+//! Requires the `trust-dns` feature (enabled by default) for the
+//! `SrvResolve` impl on `TokioAsyncResolver` to be in scope.
+#![cfg(feature = "trust-dns")]
+
 use hyper::{client::HttpConnector, Body, Client, StatusCode, Uri};
 use hyper_srv::ServiceConnector;
+use std::sync::Arc;
 use trust_dns_resolver::AsyncResolver;
 
 #[tokio::main]
 pub async fn main() {
-    let resolver = AsyncResolver::tokio_from_system_conf().unwrap();
-    let client = Client::builder().build::<_, Body>(ServiceConnector::new(HttpConnector::new(), Some(resolver)));
+    let resolver = AsyncResolver::tokio_from_system_conf().await.unwrap();
+    let client =
+        Client::builder().build::<_, Body>(ServiceConnector::new(HttpConnector::new(), Some(Arc::new(resolver))));
     let response = client.get(Uri::from_static("http://_http._tcp.mxtoolbox.com")).await.unwrap();
     // Cloudfront returns 403 but at least we have resolved SRV uri correctly.
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
@@ -3,11 +3,13 @@
 //! The exact algorithm is as following:
 //!
 //! 1) Check if a connection destination could be (theoretically) a srv record (has no port, etc).
-//! Use the underlying connector otherwise.
+//!    Use the underlying connector otherwise.
 //! 2) Try to resolve the destination host and port using provided resolver (if set). In case no
-//! srv records has been found use the underlying connector with the origin destination.
-//! 3) Use the first record resolved to create a new destination (`A`/`AAAA`) and
-//! finally pass it to the underlying connector.
+//!    srv records has been found use the underlying connector with the origin destination.
+//! 3) Order the records resolved per RFC 2782 (ascending priority, weighted random within a
+//!    priority) and use the first one to create a new destination (`A`/`AAAA`), finally passing it
+//!    to the underlying connector. Should the underlying connector refuse that destination, the
+//!    next target in the ordered list is tried until one succeeds or the list is exhausted.
 
 #![deny(missing_docs)]
 
@@ -18,24 +20,184 @@ use futures::{
     Future,
 };
 use hyper::{client::connect::Connection, service::Service, Uri};
-use std::{error::Error, fmt, pin::Pin};
-use tokio::io::{AsyncRead, AsyncWrite};
-use trust_dns_resolver::{
-    error::{ResolveError, ResolveErrorKind},
-    lookup::SrvLookup,
-    TokioAsyncResolver,
+use rand::Rng;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    error::Error,
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
 };
+use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "trust-dns")]
+use trust_dns_resolver::{error::ResolveErrorKind, TokioAsyncResolver};
+
+/// A DNS SRV record, reduced to the fields [`ServiceConnector`] needs to rank and connect to
+/// a target.
+///
+/// [`ServiceConnector`]: struct.ServiceConnector.html
+#[derive(Debug, Clone)]
+pub struct SrvRecord {
+    /// The resolved target host.
+    pub target: String,
+    /// The resolved target port.
+    pub port: u16,
+    /// Selection priority; lower values are preferred (RFC 2782).
+    pub priority: u16,
+    /// Selection weight among records sharing the same priority (RFC 2782).
+    pub weight: u16,
+}
+
+/// A pluggable source of SRV records, decoupling [`ServiceConnector`] from any one DNS
+/// implementation. Implement this to back resolution with a mock resolver (handy in tests),
+/// an alternate DNS library, or a service-discovery backend such as Consul.
+///
+/// [`ServiceConnector`]: struct.ServiceConnector.html
+pub trait SrvResolve: fmt::Debug + Send + Sync {
+    /// Resolves `name` to the set of SRV records currently published for it. An empty result
+    /// (rather than an error) is expected when the name simply has no SRV records.
+    fn resolve<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SrvRecord>, Box<dyn Error + Send + Sync>>>;
+}
+
+#[cfg(feature = "trust-dns")]
+impl SrvResolve for TokioAsyncResolver {
+    fn resolve<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<SrvRecord>, Box<dyn Error + Send + Sync>>> {
+        Box::pin(async move {
+            let lookup = match self.srv_lookup(name).await {
+                Ok(lookup) => lookup,
+                Err(err) => match err.kind() {
+                    ResolveErrorKind::NoRecordsFound {
+                        ..
+                    } => return Ok(Vec::new()),
+                    _unexpected => return Err(Box::new(err) as Box<dyn Error + Send + Sync>),
+                },
+            };
+            Ok(lookup
+                .iter()
+                .map(|record| SrvRecord {
+                    target: record.target().to_string(),
+                    port: record.port(),
+                    priority: record.priority(),
+                    weight: record.weight(),
+                })
+                .collect())
+        })
+    }
+}
+
+/// What to do when the SRV resolution timeout set via [`ServiceConnector::with_timeout`]
+/// elapses before the lookup completes.
+///
+/// [`ServiceConnector::with_timeout`]: struct.ServiceConnector.html#method.with_timeout
+#[derive(Debug, Clone, Copy)]
+pub enum ResolveTimeoutPolicy {
+    /// Proceed with the original, unresolved destination, exactly as if the resolver had
+    /// found no SRV records for it.
+    FallBackToOrigin,
+    /// Fail the connection attempt with a dedicated timeout error.
+    Fail,
+}
+
+/// A single resolved SRV target, reduced to what is needed to build a connection [`Uri`].
+#[derive(Debug, Clone)]
+struct Target {
+    host: String,
+    port: u16,
+}
+
+/// Orders a set of resolved SRV records per RFC 2782: groups are processed in ascending
+/// `priority` order, and within a group records are drawn via weighted random selection.
+/// Per the RFC, records with `weight == 0` are moved to the head of their group first, so
+/// they retain a small chance of being drawn ahead of their positive-weight siblings
+/// instead of always landing last.
+fn select_targets(records: Vec<SrvRecord>) -> Vec<Target> {
+    let mut by_priority = BTreeMap::new();
+    for record in records {
+        by_priority.entry(record.priority).or_insert_with(Vec::new).push(record);
+    }
+    let mut rng = rand::thread_rng();
+    let mut targets = Vec::new();
+    for (_priority, mut group) in by_priority {
+        group.sort_by_key(|record| record.weight != 0);
+        while !group.is_empty() {
+            let total_weight: u32 = group.iter().map(|record| u32::from(record.weight)).sum();
+            let pick = rng.gen_range(0..=total_weight);
+            let mut running_weight = 0;
+            let index = group
+                .iter()
+                .position(|record| {
+                    running_weight += u32::from(record.weight);
+                    running_weight >= pick
+                })
+                .unwrap_or_else(|| group.len() - 1);
+            let record = group.remove(index);
+            targets.push(Target {
+                host: record.target,
+                port: record.port,
+            });
+        }
+    }
+    targets
+}
+
+/// Pops targets off `targets` until one builds a valid `Uri` against `uri`, skipping any
+/// malformed ones along the way. Returns `None` once the list is exhausted.
+fn next_target_uri(uri: &Uri, targets: &mut VecDeque<Target>) -> Option<Uri> {
+    loop {
+        match targets.pop_front() {
+            Some(target) => match build_uri(uri, &target) {
+                Ok(target_uri) => break Some(target_uri),
+                Err(_bad_target) => continue,
+            },
+            None => break None,
+        }
+    }
+}
+
+/// Rebuilds `uri` with `target`'s `host:port` as the authority, keeping the original scheme
+/// and path/query intact.
+fn build_uri(uri: &Uri, target: &Target) -> Result<Uri, ServiceError> {
+    let authority = format!("{}:{}", target.host, target.port);
+    let builder = Uri::builder().authority(authority.as_str());
+    let builder = match uri.scheme() {
+        Some(scheme) => builder.scheme(scheme.clone()),
+        None => builder,
+    };
+    let builder = match uri.path_and_query() {
+        Some(path_and_query) => builder.path_and_query(path_and_query.clone()),
+        None => builder,
+    };
+    builder.build().map_err(ServiceError::inner)
+}
 
 /// A wrapper around Hyper's [`Connect`]or with ability to preresolve SRV DNS records
 /// before supplying resulting `host:port` pair to the underlying connector.
 ///
 /// [`Connect`]: ../hyper/client/connect/trait.Connect.html
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ServiceConnector<C> {
-    resolver: Option<TokioAsyncResolver>,
+    resolver: Option<Arc<dyn SrvResolve>>,
+    overrides: HashMap<String, Vec<SrvRecord>>,
+    timeout: Option<Duration>,
+    timeout_policy: ResolveTimeoutPolicy,
+    scheme_prefixes: HashMap<String, (String, String)>,
+    synthesize_names: bool,
     inner: C,
 }
 
+impl<C> fmt::Debug for ServiceConnector<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServiceConnector").finish()
+    }
+}
+
 impl<C> Service<Uri> for ServiceConnector<C>
 where
     C: Service<Uri> + Clone + Unpin,
@@ -52,21 +214,41 @@ where
     }
 
     fn call(&mut self, uri: Uri) -> Self::Future {
-        let fut = match (&self.resolver, uri.host(), uri.port()) {
-            (Some(resolver), Some(_), None) => {
-                ServiceConnectingKind::Preresolve {
-                    inner: self.inner.clone(),
-                    fut: {
-                        let resolver = resolver.clone();
-                        Box::pin(async move {
-                            let host = uri.host().expect("host was right here, now it is gone");
-                            let resolved = resolver.srv_lookup(host).await;
+        let fut = match uri.host().filter(|_| uri.port().is_none()) {
+            Some(host) => {
+                if let Some(records) = self.overrides.get(host).cloned() {
+                    ServiceConnectingKind::Preresolve {
+                        inner: self.inner.clone(),
+                        fut: Box::pin(async move { (Ok(records), uri) }),
+                    }
+                } else if let (Some(resolver), Some(name)) = (&self.resolver, self.query_name(&uri, host)) {
+                    let resolver = resolver.clone();
+                    let timeout = self.timeout;
+                    let policy = self.timeout_policy;
+                    ServiceConnectingKind::Preresolve {
+                        inner: self.inner.clone(),
+                        fut: Box::pin(async move {
+                            let resolve = resolver.resolve(&name);
+                            let resolved = match timeout {
+                                Some(timeout) => match tokio::time::timeout(timeout, resolve).await {
+                                    Ok(resolved) => resolved.map_err(ResolveFailure::Resolve),
+                                    Err(_elapsed) => match policy {
+                                        ResolveTimeoutPolicy::FallBackToOrigin => Ok(Vec::new()),
+                                        ResolveTimeoutPolicy::Fail => Err(ResolveFailure::Timeout),
+                                    },
+                                },
+                                None => resolve.await.map_err(ResolveFailure::Resolve),
+                            };
                             (resolved, uri)
-                        })
-                    },
+                        }),
+                    }
+                } else {
+                    ServiceConnectingKind::Inner {
+                        fut: self.inner.call(uri),
+                    }
                 }
             },
-            _ => {
+            None => {
                 ServiceConnectingKind::Inner {
                     fut: self.inner.call(uri),
                 }
@@ -78,23 +260,139 @@ where
 
 impl<C> ServiceConnector<C> {
     /// Creates a new instance of [`ServiceConnector`] with provided connector and
-    /// optional DNS resolver. If the resolver is set to None all connections will be
+    /// optional SRV resolver. If the resolver is set to None all connections not matched by
+    /// an override (see `with_override`) will be
     /// handled directly by the underlying connector. This allows to toggle SRV resolving
     /// mechanism without changing a type of connector used
     /// in a client (as it must be named and can not even be made into a trait object).
     ///
+    /// Any [`SrvResolve`] implementation can be plugged in here, so a mock resolver, an
+    /// alternate DNS library, or a service-discovery backend works just as well as the
+    /// trust-dns-backed one provided with the `trust-dns` feature.
+    ///
     /// [`ServiceConnector`]: struct.ServiceConnector.html
-    pub fn new(inner: C, resolver: Option<TokioAsyncResolver>) -> Self {
+    /// [`SrvResolve`]: trait.SrvResolve.html
+    pub fn new(inner: C, resolver: Option<Arc<dyn SrvResolve>>) -> Self {
         ServiceConnector {
             resolver,
+            overrides: HashMap::new(),
+            timeout: None,
+            timeout_policy: ResolveTimeoutPolicy::FallBackToOrigin,
+            scheme_prefixes: vec![
+                ("http".to_owned(), ("_http".to_owned(), "_tcp".to_owned())),
+                ("https".to_owned(), ("_https".to_owned(), "_tcp".to_owned())),
+            ]
+            .into_iter()
+            .collect(),
+            synthesize_names: false,
             inner,
         }
     }
+
+    /// Registers a static set of SRV targets for `name`, bypassing resolution entirely when
+    /// that name is looked up. An override takes priority over the resolver (if any) and is
+    /// still run through the same RFC 2782 selection and failover as resolved records.
+    ///
+    /// This is useful for local testing, air-gapped environments, or pinning traffic to a
+    /// known backend during an incident.
+    pub fn with_override<H>(
+        mut self,
+        name: impl Into<String>,
+        targets: impl IntoIterator<Item = (H, u16, u16, u16)>,
+    ) -> Self
+    where
+        H: Into<String>,
+    {
+        let records = targets
+            .into_iter()
+            .map(|(host, port, priority, weight)| SrvRecord {
+                target: host.into(),
+                port,
+                priority,
+                weight,
+            })
+            .collect();
+        self.overrides.insert(name.into(), records);
+        self
+    }
+
+    /// Bounds how long the SRV lookup phase may run before giving up on it, per
+    /// [`with_timeout_policy`]. Guards against a hung or very slow resolver stalling the whole
+    /// connection attempt indefinitely. Has no effect on names satisfied by
+    /// [`with_override`], which never reach the resolver.
+    ///
+    /// [`with_timeout_policy`]: #method.with_timeout_policy
+    /// [`with_override`]: #method.with_override
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Controls what happens when the timeout set via [`with_timeout`] elapses before the
+    /// SRV lookup completes. Defaults to [`ResolveTimeoutPolicy::FallBackToOrigin`].
+    ///
+    /// [`with_timeout`]: #method.with_timeout
+    /// [`ResolveTimeoutPolicy::FallBackToOrigin`]: enum.ResolveTimeoutPolicy.html#variant.FallBackToOrigin
+    pub fn with_timeout_policy(mut self, policy: ResolveTimeoutPolicy) -> Self {
+        self.timeout_policy = policy;
+        self
+    }
+
+    /// Registers (or overrides) the SRV `service`/`proto` prefix used for `scheme` when
+    /// [`with_synthesized_names`] is enabled. `http` and `https` are registered by default,
+    /// mapping to `_http._tcp` and `_https._tcp` respectively.
+    ///
+    /// [`with_synthesized_names`]: #method.with_synthesized_names
+    pub fn with_scheme_prefix(
+        mut self,
+        scheme: impl Into<String>,
+        service: impl Into<String>,
+        proto: impl Into<String>,
+    ) -> Self {
+        self.scheme_prefixes.insert(scheme.into(), (service.into(), proto.into()));
+        self
+    }
+
+    /// Toggles synthesizing the SRV query name from the destination's scheme and host (via
+    /// the table set up with [`with_scheme_prefix`]) instead of requiring callers to spell
+    /// out the raw SRV label (`_http._tcp.example.com`) themselves. Disabled by default, so
+    /// the original explicit-name behavior is unaffected unless opted into.
+    ///
+    /// A destination whose scheme has no registered prefix falls back to the underlying
+    /// connector untouched, the same as if no resolver were configured at all.
+    ///
+    /// [`with_scheme_prefix`]: #method.with_scheme_prefix
+    pub fn with_synthesized_names(mut self, enabled: bool) -> Self {
+        self.synthesize_names = enabled;
+        self
+    }
+
+    /// Resolves the name to query the resolver with: `host` verbatim by default, or a
+    /// synthesized `_service._proto.host` label when [`with_synthesized_names`] is enabled
+    /// and `uri`'s scheme has a registered prefix. `None` means the scheme has no registered
+    /// prefix and SRV resolution should be skipped entirely.
+    ///
+    /// [`with_synthesized_names`]: #method.with_synthesized_names
+    fn query_name(&self, uri: &Uri, host: &str) -> Option<String> {
+        if !self.synthesize_names {
+            return Some(host.to_owned());
+        }
+        let (service, proto) = self.scheme_prefixes.get(uri.scheme_str()?)?;
+        Some(format!("{}.{}.{}", service, proto, host))
+    }
+}
+
+/// The reason SRV resolution did not produce a set of records to select from.
+#[derive(Debug)]
+enum ResolveFailure {
+    Resolve(Box<dyn Error + Send + Sync>),
+    Timeout,
 }
 
 #[derive(Debug)]
 enum ServiceErrorKind {
-    Resolve(ResolveError),
+    Resolve(Box<dyn Error + Send + Sync>),
+    ResolveTimeout,
     Inner(Box<dyn Error + Send + Sync>),
 }
 
@@ -104,16 +402,11 @@ enum ServiceErrorKind {
 #[derive(Debug)]
 pub struct ServiceError(ServiceErrorKind);
 
-impl From<ResolveError> for ServiceError {
-    fn from(error: ResolveError) -> Self {
-        ServiceError(ServiceErrorKind::Resolve(error))
-    }
-}
-
 impl fmt::Display for ServiceError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self.0 {
             ServiceErrorKind::Resolve(err) => fmt::Display::fmt(err, f),
+            ServiceErrorKind::ResolveTimeout => write!(f, "SRV resolution timed out"),
             ServiceErrorKind::Inner(err) => fmt::Display::fmt(err, f),
         }
     }
@@ -122,7 +415,7 @@ impl fmt::Display for ServiceError {
 impl Error for ServiceError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self.0 {
-            ServiceErrorKind::Resolve(_) => None,
+            ServiceErrorKind::Resolve(_) | ServiceErrorKind::ResolveTimeout => None,
             ServiceErrorKind::Inner(err) => Some(err.as_ref()),
         }
     }
@@ -135,6 +428,13 @@ impl ServiceError {
     {
         ServiceError(ServiceErrorKind::Inner(inner.into()))
     }
+
+    fn resolve(failure: ResolveFailure) -> Self {
+        match failure {
+            ResolveFailure::Resolve(err) => ServiceError(ServiceErrorKind::Resolve(err)),
+            ResolveFailure::Timeout => ServiceError(ServiceErrorKind::ResolveTimeout),
+        }
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -144,7 +444,13 @@ where
 {
     Preresolve {
         inner: C,
-        fut: BoxFuture<'static, (Result<SrvLookup, ResolveError>, Uri)>,
+        fut: BoxFuture<'static, (Result<Vec<SrvRecord>, ResolveFailure>, Uri)>,
+    },
+    Targets {
+        inner: C,
+        uri: Uri,
+        targets: VecDeque<Target>,
+        fut: C::Future,
     },
     Inner {
         fut: C::Future,
@@ -170,7 +476,7 @@ where
 
 impl<C> Future for ServiceConnecting<C>
 where
-    C: Service<Uri> + Unpin,
+    C: Service<Uri> + Clone + Unpin,
     C::Response: AsyncRead + AsyncWrite + Connection + Unpin + Send + 'static,
     C::Error: Into<Box<dyn Error + Send + Sync>>,
     C::Future: Unpin + Send,
@@ -184,40 +490,206 @@ where
                 fut,
             } => {
                 let (res, uri) = ready!(Pin::new(fut).poll(ctx));
-                let response = res.map(Some).or_else(|err| {
-                    match err.kind() {
-                        ResolveErrorKind::NoRecordsFound {
-                            ..
-                        } => Ok(None),
-                        _unexpected => Err(ServiceError(ServiceErrorKind::Resolve(err))),
-                    }
-                })?;
-                let uri = match response.as_ref().and_then(|response| response.iter().next()) {
-                    Some(srv) => {
-                        let authority = format!("{}:{}", srv.target(), srv.port());
-                        let builder = Uri::builder().authority(authority.as_str());
-                        let builder = match uri.scheme() {
-                            Some(scheme) => builder.scheme(scheme.clone()),
-                            None => builder,
-                        };
-                        let builder = match uri.path_and_query() {
-                            Some(path_and_query) => builder.path_and_query(path_and_query.clone()),
-                            None => builder,
-                        };
-                        builder.build().map_err(ServiceError::inner)?
-                    },
-                    None => uri,
-                };
+                let records = res.map_err(ServiceError::resolve)?;
+                let mut targets: VecDeque<Target> = select_targets(records).into();
+                // A malformed target must not abort resolution outright; skip it (and any
+                // others) until one builds cleanly, falling back to the origin destination
+                // once the list is exhausted, same as if no records had been found.
+                let first = next_target_uri(&uri, &mut targets);
                 {
-                    *self = ServiceConnecting(ServiceConnectingKind::Inner {
-                        fut: inner.call(uri),
-                    });
+                    *self = match first {
+                        Some(target_uri) => ServiceConnecting(ServiceConnectingKind::Targets {
+                            inner: inner.clone(),
+                            fut: inner.call(target_uri),
+                            uri,
+                            targets,
+                        }),
+                        None => ServiceConnecting(ServiceConnectingKind::Inner {
+                            fut: inner.call(uri),
+                        }),
+                    };
                 }
                 self.poll(ctx)
             },
+            ServiceConnectingKind::Targets {
+                inner,
+                uri,
+                targets,
+                fut,
+            } => {
+                match Pin::new(&mut *fut).poll(ctx) {
+                    Poll::Pending => Poll::Pending,
+                    Poll::Ready(Ok(response)) => Poll::Ready(Ok(response)),
+                    Poll::Ready(Err(err)) => {
+                        // A single malformed target must not abandon the rest of the failover
+                        // chain, so skip past it (and any others) until one builds cleanly.
+                        match next_target_uri(uri, targets) {
+                            Some(target_uri) => {
+                                *fut = inner.call(target_uri);
+                                self.poll(ctx)
+                            },
+                            None => Poll::Ready(Err(ServiceError::inner(err))),
+                        }
+                    },
+                }
+            },
             ServiceConnectingKind::Inner {
                 fut,
             } => Pin::new(fut).poll(ctx).map_err(ServiceError::inner),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A connection stub satisfying Hyper's `Connection` bound, doing no actual I/O.
+    struct MockConn;
+
+    impl AsyncRead for MockConn {
+        fn poll_read(self: Pin<&mut Self>, _ctx: &mut Context, _buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+            Poll::Ready(Ok(0))
+        }
+    }
+
+    impl AsyncWrite for MockConn {
+        fn poll_write(self: Pin<&mut Self>, _ctx: &mut Context, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _ctx: &mut Context) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _ctx: &mut Context) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl Connection for MockConn {
+        fn connected(&self) -> hyper::client::connect::Connected {
+            hyper::client::connect::Connected::new()
+        }
+    }
+
+    /// A stub inner connector that records the last `Uri` it was asked to connect to.
+    #[derive(Clone)]
+    struct MockConnector {
+        last_uri: Arc<Mutex<Option<Uri>>>,
+    }
+
+    impl Service<Uri> for MockConnector {
+        type Response = MockConn;
+        type Error = Box<dyn Error + Send + Sync>;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _ctx: &mut Context) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, uri: Uri) -> Self::Future {
+            *self.last_uri.lock().unwrap() = Some(uri);
+            Box::pin(async { Ok(MockConn) })
+        }
+    }
+
+    /// A resolver that never completes, for exercising the timeout/fallback path.
+    #[derive(Debug)]
+    struct PendingResolver;
+
+    impl SrvResolve for PendingResolver {
+        fn resolve<'a>(
+            &'a self,
+            _name: &'a str,
+        ) -> BoxFuture<'a, Result<Vec<SrvRecord>, Box<dyn Error + Send + Sync>>> {
+            Box::pin(futures::future::pending())
+        }
+    }
+
+    fn record(target: &str, port: u16, priority: u16, weight: u16) -> SrvRecord {
+        SrvRecord {
+            target: target.to_owned(),
+            port,
+            priority,
+            weight,
+        }
+    }
+
+    #[test]
+    fn select_targets_orders_groups_by_ascending_priority() {
+        let records = vec![
+            record("c", 3, 30, 1),
+            record("a", 1, 10, 1),
+            record("b", 2, 20, 1),
+        ];
+        let targets = select_targets(records);
+        let hosts: Vec<&str> = targets.iter().map(|target| target.host.as_str()).collect();
+        assert_eq!(hosts, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn select_targets_gives_zero_weight_records_a_chance_to_be_drawn_first() {
+        let records = vec![record("positive", 1, 0, 10), record("zero", 2, 0, 0)];
+        let drawn_first = (0..200)
+            .map(|_| select_targets(records.clone()))
+            .any(|targets| targets[0].host == "zero");
+        assert!(
+            drawn_first,
+            "a weight-0 record should occasionally be drawn ahead of its positive-weight sibling"
+        );
+    }
+
+    #[test]
+    fn query_name_defaults_to_verbatim_host() {
+        let connector = ServiceConnector::new((), None);
+        let uri = Uri::from_static("http://example.com");
+        assert_eq!(connector.query_name(&uri, "example.com"), Some("example.com".to_owned()));
+    }
+
+    #[test]
+    fn query_name_synthesizes_service_proto_label_when_enabled() {
+        let connector = ServiceConnector::new((), None).with_synthesized_names(true);
+        let uri = Uri::from_static("http://example.com");
+        assert_eq!(
+            connector.query_name(&uri, "example.com"),
+            Some("_http._tcp.example.com".to_owned())
+        );
+    }
+
+    #[test]
+    fn query_name_is_none_for_a_scheme_with_no_registered_prefix_when_synthesizing() {
+        let connector = ServiceConnector::new((), None).with_synthesized_names(true);
+        let uri = Uri::from_static("ftp://example.com");
+        assert_eq!(connector.query_name(&uri, "example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn override_targets_are_used_instead_of_the_resolver() {
+        let last_uri = Arc::new(Mutex::new(None));
+        let connector = MockConnector {
+            last_uri: last_uri.clone(),
+        };
+        let mut service = ServiceConnector::new(connector, None).with_override("example.com", vec![("backend.internal", 9999, 0, 0)]);
+        service.call(Uri::from_static("http://example.com/path")).await.unwrap();
+        let called = last_uri.lock().unwrap().clone().unwrap();
+        assert_eq!(called.host(), Some("backend.internal"));
+        assert_eq!(called.port_u16(), Some(9999));
+    }
+
+    #[tokio::test]
+    async fn resolve_timeout_falls_back_to_the_origin_destination() {
+        let last_uri = Arc::new(Mutex::new(None));
+        let connector = MockConnector {
+            last_uri: last_uri.clone(),
+        };
+        let mut service = ServiceConnector::new(connector, Some(Arc::new(PendingResolver)))
+            .with_timeout(Duration::from_millis(10))
+            .with_timeout_policy(ResolveTimeoutPolicy::FallBackToOrigin);
+        let uri = Uri::from_static("http://example.com/path");
+        service.call(uri.clone()).await.unwrap();
+        let called = last_uri.lock().unwrap().clone().unwrap();
+        assert_eq!(called, uri);
+    }
+}